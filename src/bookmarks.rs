@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Result, Write};
+use std::path::PathBuf;
+
+pub struct BookmarkStore {
+    path: PathBuf,
+    bookmarks: HashMap<String, usize>,
+}
+
+impl BookmarkStore {
+    pub fn load(path: PathBuf) -> Result<Self> {
+        let mut bookmarks = HashMap::new();
+        if path.exists() {
+            let file = OpenOptions::new().read(true).write(false).open(&path)?;
+            for line in BufReader::new(file).lines() {
+                let line = line?;
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+                if let Some(space) = line.find(' ') {
+                    let (id, index) = line.split_at(space);
+                    if let Ok(index) = index.trim().parse() {
+                        bookmarks.insert(id.trim().to_string(), index);
+                    }
+                }
+            }
+        }
+        Ok(Self { path, bookmarks })
+    }
+
+    pub fn get(&self, id: &str) -> usize {
+        self.bookmarks.get(id).copied().unwrap_or(0)
+    }
+
+    pub fn set(&mut self, id: &str, index: usize) {
+        self.bookmarks.insert(id.to_string(), index);
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&self.path)?;
+        for (id, index) in &self.bookmarks {
+            writeln!(file, "{} {}", id, index)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("rfid-audio-test-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn missing_file_starts_empty() {
+        let store = BookmarkStore::load(temp_path("missing")).unwrap();
+        assert_eq!(store.get("deadbeef"), 0);
+    }
+
+    #[test]
+    fn save_then_load_round_trips() {
+        let path = temp_path("roundtrip");
+        let _ = std::fs::remove_file(&path);
+
+        let mut store = BookmarkStore::load(path.clone()).unwrap();
+        store.set("deadbeef", 3);
+        store.set("cafef00d", 0);
+        store.save().unwrap();
+
+        let reloaded = BookmarkStore::load(path.clone()).unwrap();
+        assert_eq!(reloaded.get("deadbeef"), 3);
+        assert_eq!(reloaded.get("cafef00d"), 0);
+        assert_eq!(reloaded.get("unknown"), 0);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,127 @@
+use hal::sysfs_gpio::{Direction, Error};
+use hal::Pin;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Button {
+    Next,
+    Previous,
+    Pause,
+    VolumeUp,
+    VolumeDown,
+}
+
+// Idle high, pulled low while pressed.
+struct DebouncedPin {
+    pin: Pin,
+    last_raw: bool,
+    pressed: bool,
+}
+
+impl DebouncedPin {
+    fn new(number: u64) -> Result<Self, Error> {
+        let pin = Pin::new(number);
+        pin.export()?;
+        while !pin.is_exported() {}
+        pin.set_direction(Direction::In)?;
+        Ok(Self {
+            pin,
+            last_raw: false,
+            pressed: false,
+        })
+    }
+
+    // True only on the poll where the button settles into the pressed state.
+    fn poll_pressed_edge(&mut self) -> Result<bool, Error> {
+        let raw_pressed = self.pin.get_value()? == 0;
+        let newly_pressed = debounce_edge(self.last_raw, raw_pressed, &mut self.pressed);
+        self.last_raw = raw_pressed;
+        Ok(newly_pressed)
+    }
+}
+
+// Pure debounce step, split out of poll_pressed_edge so it's testable
+// without a real GPIO pin. Updates `pressed` in place and returns whether
+// this poll is the one where the button just settled into pressed.
+fn debounce_edge(last_raw: bool, raw_pressed: bool, pressed: &mut bool) -> bool {
+    let stable = raw_pressed == last_raw;
+    let newly_pressed = stable && raw_pressed && !*pressed;
+    if stable {
+        *pressed = raw_pressed;
+    }
+    newly_pressed
+}
+
+pub struct Buttons {
+    next: DebouncedPin,
+    prev: DebouncedPin,
+    pause: DebouncedPin,
+    vol_up: DebouncedPin,
+    vol_down: DebouncedPin,
+}
+
+impl Buttons {
+    pub fn new(
+        next: u64,
+        prev: u64,
+        pause: u64,
+        vol_up: u64,
+        vol_down: u64,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            next: DebouncedPin::new(next)?,
+            prev: DebouncedPin::new(prev)?,
+            pause: DebouncedPin::new(pause)?,
+            vol_up: DebouncedPin::new(vol_up)?,
+            vol_down: DebouncedPin::new(vol_down)?,
+        })
+    }
+
+    pub fn pressed(&mut self) -> Result<Vec<Button>, Error> {
+        let mut pressed = vec![];
+        if self.next.poll_pressed_edge()? {
+            pressed.push(Button::Next);
+        }
+        if self.prev.poll_pressed_edge()? {
+            pressed.push(Button::Previous);
+        }
+        if self.pause.poll_pressed_edge()? {
+            pressed.push(Button::Pause);
+        }
+        if self.vol_up.poll_pressed_edge()? {
+            pressed.push(Button::VolumeUp);
+        }
+        if self.vol_down.poll_pressed_edge()? {
+            pressed.push(Button::VolumeDown);
+        }
+        Ok(pressed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noisy_single_read_is_not_reported() {
+        let mut pressed = false;
+        assert!(!debounce_edge(false, true, &mut pressed));
+        assert!(!pressed);
+    }
+
+    #[test]
+    fn stable_press_reports_one_rising_edge_then_nothing() {
+        let mut pressed = false;
+        assert!(!debounce_edge(false, true, &mut pressed));
+        assert!(debounce_edge(true, true, &mut pressed));
+        assert!(!debounce_edge(true, true, &mut pressed));
+    }
+
+    #[test]
+    fn release_then_press_again_reports_a_second_edge() {
+        let mut pressed = true;
+        assert!(!debounce_edge(true, false, &mut pressed));
+        assert!(!debounce_edge(false, false, &mut pressed));
+        assert!(!debounce_edge(false, true, &mut pressed));
+        assert!(debounce_edge(true, true, &mut pressed));
+    }
+}
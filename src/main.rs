@@ -7,7 +7,9 @@ extern crate syslog;
 extern crate hex;
 extern crate linux_embedded_hal as hal;
 extern crate mfrc522;
+extern crate rand;
 extern crate rodio;
+extern crate walkdir;
 
 use clap::{App, Arg, ArgMatches};
 use core::convert::TryFrom;
@@ -17,6 +19,9 @@ use hal::{Pin, Spidev};
 use log::LevelFilter;
 use mfrc522::Mfrc522;
 use nix::sys::signal::{signal, SigHandler, Signal};
+use rand::rngs::SmallRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
 use std::collections::HashMap;
 use std::env;
 use std::ffi::OsStr;
@@ -24,13 +29,31 @@ use std::fs::OpenOptions;
 use std::io::{BufRead, BufReader, Error, ErrorKind, Result};
 use std::path::{Path, PathBuf};
 use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
+use walkdir::{DirEntry, WalkDir};
+
+mod bookmarks;
+mod buttons;
+mod status;
+
+use bookmarks::BookmarkStore;
+use buttons::{Button, Buttons};
+use status::{Event, PlayerStatus, StatusSink, StdoutSink, UnixSocketSink};
+
+// Set by handle_signals; the handler itself must not touch the bookmark
+// store's Mutex or it could deadlock against the main thread.
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+const RFID_POLL_INTERVAL: Duration = Duration::from_millis(1000);
+const BUTTON_POLL_INTERVAL: Duration = Duration::from_millis(25);
 
 extern "C" fn handle_signals(signal: libc::c_int) {
     let signal = Signal::try_from(signal).unwrap();
     info!("Signal {} received. Quitting.", signal.as_str());
-    process::exit(1);
+    SHUTDOWN_REQUESTED.store(true, Ordering::SeqCst);
 }
 
 fn setup_rfid_reader() -> std::result::Result<Mfrc522<Spidev, Pin>, hal::sysfs_gpio::Error> {
@@ -69,6 +92,67 @@ fn setup_signals() {
         signal(Signal::SIGINT, handler).unwrap();
         signal(Signal::SIGHUP, handler).unwrap();
         signal(Signal::SIGQUIT, handler).unwrap();
+        signal(Signal::SIGTERM, handler).unwrap();
+    }
+}
+
+fn setup_buttons(matches: &ArgMatches) -> Result<Option<Buttons>> {
+    let pins = (
+        matches.value_of("btn_next"),
+        matches.value_of("btn_prev"),
+        matches.value_of("btn_pause"),
+        matches.value_of("btn_vol_up"),
+        matches.value_of("btn_vol_down"),
+    );
+    let parse_pin = |value: &str| {
+        value
+            .parse::<u64>()
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, format!("'{}' is not a gpio pin number", value)))
+    };
+    match pins {
+        (Some(next), Some(prev), Some(pause), Some(vol_up), Some(vol_down)) => {
+            let buttons = Buttons::new(
+                parse_pin(next)?,
+                parse_pin(prev)?,
+                parse_pin(pause)?,
+                parse_pin(vol_up)?,
+                parse_pin(vol_down)?,
+            )
+            .map_err(|err| Error::new(ErrorKind::Other, err.to_string()))?;
+            Ok(Some(buttons))
+        }
+        (None, None, None, None, None) => Ok(None),
+        _ => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "btn_next, btn_prev, btn_pause, btn_vol_up and btn_vol_down must all be set, or none of them",
+        )),
+    }
+}
+
+fn setup_bookmarks(matches: &ArgMatches) -> Result<Option<Arc<Mutex<BookmarkStore>>>> {
+    match matches.value_of_os("state_file") {
+        Some(state_file) => {
+            let store = BookmarkStore::load(PathBuf::from(state_file))?;
+            Ok(Some(Arc::new(Mutex::new(store))))
+        }
+        None => Ok(None),
+    }
+}
+
+fn setup_status_sink(matches: &ArgMatches) -> Result<Option<Box<dyn StatusSink>>> {
+    match matches.value_of("status_sink") {
+        Some("stdout") => Ok(Some(Box::new(StdoutSink) as Box<dyn StatusSink>)),
+        Some(socket_path) => {
+            let sink = UnixSocketSink::connect(Path::new(socket_path))?;
+            Ok(Some(Box::new(sink) as Box<dyn StatusSink>))
+        }
+        None => Ok(None),
+    }
+}
+
+fn emit_status(status_sink: &mut Option<Box<dyn StatusSink>>, event: Event<'_>) {
+    if let Some(status_sink) = status_sink.as_mut() {
+        status_sink.emit(event);
     }
 }
 
@@ -78,7 +162,44 @@ fn files_directory(arg_dir: Option<&str>) -> Result<String> {
     Ok(dir)
 }
 
-fn read_maps(mapping_file: &OsStr) -> Result<HashMap<String, String>> {
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PlayMode {
+    InOrder,
+    Random,
+    RandomKeepFirst(usize),
+}
+
+fn parse_mode(token: &str) -> Result<PlayMode> {
+    let token = token.trim_start_matches('[').trim_end_matches(']');
+    if token == "random" {
+        Ok(PlayMode::Random)
+    } else if let Some(count) = token.strip_prefix("random:") {
+        let count = count.parse().map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("'{}' is not a valid random count", count),
+            )
+        })?;
+        Ok(PlayMode::RandomKeepFirst(count))
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("'{}' is not a known play mode", token),
+        ))
+    }
+}
+
+struct Mapping {
+    path: String,
+    mode: PlayMode,
+}
+
+enum MapEntry {
+    Playlist(Mapping),
+    Lock,
+}
+
+fn read_maps(mapping_file: &OsStr) -> Result<HashMap<String, MapEntry>> {
     info!("Reading mapping file");
     let mut maps = HashMap::new();
     let mapping_file = OpenOptions::new()
@@ -92,7 +213,7 @@ fn read_maps(mapping_file: &OsStr) -> Result<HashMap<String, String>> {
         if line.is_empty() || line.find('#') == Some(0) {
             continue;
         }
-        let (key, file) = match line.find(' ') {
+        let (key, rest) = match line.find(' ') {
             Some(indx) => {
                 let (k, v) = line.split_at(indx);
                 (k.trim(), v.trim())
@@ -104,15 +225,36 @@ fn read_maps(mapping_file: &OsStr) -> Result<HashMap<String, String>> {
                 ));
             }
         };
-        debug!("map: {} - {}", key, file);
-        maps.insert(key.to_string(), file.to_string());
+        if rest == "[lock]" {
+            debug!("map: {} - lock", key);
+            maps.insert(key.to_string(), MapEntry::Lock);
+            continue;
+        }
+        let (file, mode) = match rest.rfind('[') {
+            Some(indx) if rest.ends_with(']') => {
+                let (p, m) = rest.split_at(indx);
+                match parse_mode(m.trim()) {
+                    Ok(mode) => (p.trim(), mode),
+                    Err(_) => (rest, PlayMode::InOrder),
+                }
+            }
+            _ => (rest, PlayMode::InOrder),
+        };
+        debug!("map: {} - {} ({:?})", key, file, mode);
+        maps.insert(
+            key.to_string(),
+            MapEntry::Playlist(Mapping {
+                path: file.to_string(),
+                mode,
+            }),
+        );
     }
     Ok(maps)
 }
 
 struct FileMapper {
     files_dir: PathBuf,
-    mapping: HashMap<String, String>,
+    mapping: HashMap<String, MapEntry>,
 }
 
 impl FileMapper {
@@ -122,12 +264,35 @@ impl FileMapper {
         Ok(FileMapper { files_dir, mapping })
     }
 
-    pub fn get_file(&self, hex_code: &str) -> Option<PathBuf> {
-        let file_name = self.mapping.get(hex_code);
-        file_name.map(|file_name| self.files_dir.join(file_name))
+    pub fn get_file(&self, hex_code: &str) -> Option<(PathBuf, PlayMode)> {
+        match self.mapping.get(hex_code) {
+            Some(MapEntry::Playlist(mapping)) => {
+                Some((self.files_dir.join(&mapping.path), mapping.mode))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_lock(&self, hex_code: &str) -> bool {
+        matches!(self.mapping.get(hex_code), Some(MapEntry::Lock))
     }
 }
 
+fn is_hidden(entry: &DirEntry) -> bool {
+    entry
+        .file_name()
+        .to_str()
+        .map(|name| name.starts_with('.'))
+        .unwrap_or(false)
+}
+
+fn is_audio_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .map(|ext| matches!(ext.to_lowercase().as_str(), "mp3" | "ogg" | "flac" | "wav"))
+        .unwrap_or(false)
+}
+
 struct PlayList {
     songs: Vec<PathBuf>,
     index: usize,
@@ -140,13 +305,33 @@ impl PlayList {
             index: 0,
         }
     }
-    pub fn new(songs: impl Iterator<Item = PathBuf>) -> Self {
-        let mut songs: Vec<PathBuf> = songs.collect();
+    pub fn new(path: &Path, mode: PlayMode) -> Self {
+        let mut songs: Vec<PathBuf> = if path.is_dir() {
+            WalkDir::new(path)
+                .into_iter()
+                .filter_entry(|entry| !is_hidden(entry))
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().is_file())
+                .map(|entry| entry.into_path())
+                .filter(|path| is_audio_file(path))
+                .collect()
+        } else {
+            vec![path.to_path_buf()]
+        };
         songs.sort();
-        Self {
-            songs: songs,
-            index: 0,
+        match mode {
+            PlayMode::InOrder => {}
+            PlayMode::Random => {
+                let mut rng = SmallRng::from_entropy();
+                songs.shuffle(&mut rng);
+            }
+            PlayMode::RandomKeepFirst(keep) => {
+                let keep = keep.min(songs.len());
+                let mut rng = SmallRng::from_entropy();
+                songs[keep..].shuffle(&mut rng);
+            }
         }
+        Self { songs, index: 0 }
     }
     pub fn current_song(&self) -> Option<&Path> {
         if self.done() {
@@ -159,71 +344,206 @@ impl PlayList {
         self.index == self.songs.len()
     }
     pub fn advance(&mut self) -> Option<&Path> {
-        self.index += 1;
+        self.index = (self.index + 1).min(self.songs.len());
         self.current_song()
     }
+    pub fn previous(&mut self) -> Option<&Path> {
+        self.index = self.index.saturating_sub(1);
+        self.current_song()
+    }
+    // Only meaningful for PlayMode::InOrder; Random/RandomKeepFirst
+    // reshuffle on every scan so a bookmarked index may land elsewhere.
+    pub fn set_index(&mut self, index: usize) {
+        self.index = index.min(self.songs.len());
+    }
+}
+
+fn save_bookmark(bookmarks: &Option<Arc<Mutex<BookmarkStore>>>, id: &str, index: usize) {
+    if let Some(bookmarks) = bookmarks {
+        if let Ok(mut store) = bookmarks.lock() {
+            store.set(id, index);
+            if let Err(err) = store.save() {
+                error!("Error saving bookmark for {}: {}", id, err);
+            }
+        }
+    }
+}
+
+fn set_player_status(
+    player_status: &mut PlayerStatus,
+    status_sink: &mut Option<Box<dyn StatusSink>>,
+    new_status: PlayerStatus,
+) {
+    *player_status = new_status;
+    emit_status(status_sink, Event::Status { state: new_status });
+}
+
+fn toggle_pause(
+    sink: &rodio::Sink,
+    player_status: &mut PlayerStatus,
+    status_sink: &mut Option<Box<dyn StatusSink>>,
+) {
+    if sink.is_paused() {
+        sink.play();
+        set_player_status(player_status, status_sink, PlayerStatus::NowPlaying);
+        emit_status(status_sink, Event::Resumed);
+    } else {
+        sink.pause();
+        set_player_status(player_status, status_sink, PlayerStatus::Paused);
+        emit_status(status_sink, Event::Paused);
+    }
 }
 
 fn main_loop(
     device: rodio::Device,
     mut mfrc522: Mfrc522<Spidev, Pin>,
     file_mapper: FileMapper,
+    mut buttons: Option<Buttons>,
+    bookmarks: Option<Arc<Mutex<BookmarkStore>>>,
+    mut status_sink: Option<Box<dyn StatusSink>>,
 ) -> Result<()> {
     let mut playing: Option<String> = None;
     let mut current_sink: Option<rodio::Sink> = None;
     let mut playlist: PlayList = PlayList::empty();
     let mut count_no_card: u32 = 0;
+    let mut locked = false;
+    let mut lock_tag_scanned = false;
+    let mut volume: f32 = 1.0;
+    let mut player_status = PlayerStatus::Stopped;
+    emit_status(&mut status_sink, Event::Status { state: player_status });
+    let mut last_rfid_poll = Instant::now() - RFID_POLL_INTERVAL;
     loop {
+        if SHUTDOWN_REQUESTED.load(Ordering::SeqCst) {
+            if let Some(id) = playing.as_ref() {
+                save_bookmark(&bookmarks, id, playlist.index);
+            }
+            info!("Shutting down");
+            process::exit(0);
+        }
+        if let Some(buttons) = buttons.as_mut() {
+            match buttons.pressed() {
+                Ok(pressed) => {
+                    for button in pressed {
+                        match button {
+                            Button::Next => {
+                                playlist.advance();
+                                if let Some(id) = playing.as_ref() {
+                                    save_bookmark(&bookmarks, id, playlist.index);
+                                }
+                                if let Some(sink) = current_sink.take() {
+                                    sink.stop();
+                                }
+                            }
+                            Button::Previous => {
+                                playlist.previous();
+                                if let Some(id) = playing.as_ref() {
+                                    save_bookmark(&bookmarks, id, playlist.index);
+                                }
+                                if let Some(sink) = current_sink.take() {
+                                    sink.stop();
+                                }
+                            }
+                            Button::Pause => {
+                                if let Some(ref sink) = current_sink {
+                                    toggle_pause(sink, &mut player_status, &mut status_sink);
+                                }
+                            }
+                            Button::VolumeUp => {
+                                volume = (volume + 0.1).min(2.0);
+                                if let Some(ref sink) = current_sink {
+                                    sink.set_volume(volume);
+                                }
+                            }
+                            Button::VolumeDown => {
+                                volume = (volume - 0.1).max(0.0);
+                                if let Some(ref sink) = current_sink {
+                                    sink.set_volume(volume);
+                                }
+                            }
+                        }
+                    }
+                }
+                Err(err) => error!("Error reading buttons: {:?}", err),
+            }
+        }
+        if last_rfid_poll.elapsed() < RFID_POLL_INTERVAL {
+            thread::sleep(BUTTON_POLL_INTERVAL);
+            continue;
+        }
+        last_rfid_poll = Instant::now();
         if let Ok(uid) = mfrc522.reqa().and_then(|atqa| mfrc522.select(&atqa)) {
             let last_count_no_card = count_no_card;
             count_no_card = 0;
             let encoded_id = hex::encode(uid.bytes());
+            if file_mapper.is_lock(&encoded_id) {
+                if !lock_tag_scanned {
+                    locked = !locked;
+                    lock_tag_scanned = true;
+                    info!("Lock {}", if locked { "engaged" } else { "released" });
+                }
+                continue;
+            }
+            lock_tag_scanned = false;
             if !playlist.done() && Some(&encoded_id) == playing.as_ref() {
                 if let Some(ref current_sink) = current_sink {
-                    if last_count_no_card >= 2 {
-                        if current_sink.is_paused() {
-                            current_sink.play();
-                        } else {
-                            current_sink.pause();
-                        }
+                    if !locked && last_count_no_card >= 2 {
+                        toggle_pause(current_sink, &mut player_status, &mut status_sink);
                     }
                 }
                 continue;
             }
-            if let Some(sink) = current_sink.take() {
-                sink.stop();
-            }
-            let fname = file_mapper.get_file(&encoded_id);
-            let fname = match fname {
-                Some(file_name) => file_name,
-                None => {
-                    error!("Card with id {} is not mapped", encoded_id);
+            if !locked {
+                if let Some(previous_id) = playing.as_ref() {
+                    save_bookmark(&bookmarks, previous_id, playlist.index);
+                }
+                if let Some(sink) = current_sink.take() {
+                    sink.stop();
+                }
+                let mapped = file_mapper.get_file(&encoded_id);
+                let (fname, mode) = match mapped {
+                    Some(mapped) => mapped,
+                    None => {
+                        error!("Card with id {} is not mapped", encoded_id);
+                        emit_status(&mut status_sink, Event::CardUnmapped { id: &encoded_id });
+                        continue;
+                    }
+                };
+                if !fname.exists() {
+                    error!(
+                        "Mapped path {:?} for card with id {} does not exist",
+                        fname, encoded_id
+                    );
                     continue;
                 }
-            };
-            if !fname.exists() {
-                error!(
-                    "Mapped path {:?} for card with id {} does not exist",
-                    fname, encoded_id
-                );
-                continue;
-            }
-            if fname.is_dir() {
-                let entries = fname.read_dir()?;
-                playlist = PlayList::new(
-                    entries.map(|dir_entry| dir_entry.expect("can't read direntry").path()),
-                );
-            } else {
-                playlist = PlayList::new(std::iter::once(fname).map(|fp| fp.to_path_buf()));
+                emit_status(&mut status_sink, Event::CardDetected { id: &encoded_id });
+                playlist = PlayList::new(&fname, mode);
+                if let Some(bookmarks) = bookmarks.as_ref() {
+                    if let Ok(store) = bookmarks.lock() {
+                        playlist.set_index(store.get(&encoded_id));
+                    }
+                }
+                playing.replace(encoded_id);
             }
-            playing.replace(encoded_id);
+            // else: locked with a different card present, ignore the swap
         } else {
             count_no_card += 1;
+            lock_tag_scanned = false;
         }
         if let Some(sink) = current_sink.as_ref() {
             if sink.empty() {
                 current_sink.take();
                 playlist.advance();
+                if let Some(id) = playing.as_ref() {
+                    save_bookmark(&bookmarks, id, playlist.index);
+                    if playlist.done() {
+                        set_player_status(
+                            &mut player_status,
+                            &mut status_sink,
+                            PlayerStatus::Stopped,
+                        );
+                        emit_status(&mut status_sink, Event::PlaylistFinished { id });
+                    }
+                }
             }
         }
         if current_sink.is_none() && !playlist.done() {
@@ -233,8 +553,26 @@ fn main_loop(
             match OpenOptions::new().read(true).write(false).open(&fname) {
                 Ok(opened_file) => {
                     if let Ok(new_sink) = rodio::play_once(&device, BufReader::new(opened_file)) {
+                        new_sink.set_volume(volume);
                         current_sink.replace(new_sink);
                         info!("Playing {} ", fname.display());
+                        set_player_status(
+                            &mut player_status,
+                            &mut status_sink,
+                            PlayerStatus::NowPlaying,
+                        );
+                        if let Some(id) = playing.as_ref() {
+                            let path = fname.to_string_lossy();
+                            emit_status(
+                                &mut status_sink,
+                                Event::NowPlaying {
+                                    id,
+                                    path: &path,
+                                    index: playlist.index,
+                                    total: playlist.songs.len(),
+                                },
+                            );
+                        }
                     }
                 }
                 Err(error) => {
@@ -242,7 +580,7 @@ fn main_loop(
                 }
             }
         }
-        thread::sleep(Duration::from_millis(1000));
+        thread::sleep(BUTTON_POLL_INTERVAL);
     }
 }
 
@@ -258,8 +596,14 @@ fn run(matches: ArgMatches) -> Result<()> {
         matches.value_of("directory"),
         matches.value_of_os("mapping_file").unwrap(),
     )?;
+    debug!("Setup buttons");
+    let buttons = setup_buttons(&matches)?;
+    debug!("Setup bookmarks");
+    let bookmarks = setup_bookmarks(&matches)?;
+    debug!("Setup status sink");
+    let status_sink = setup_status_sink(&matches)?;
     info!("Rfid player started");
-    main_loop(audio_device, mfrc522, mapper)
+    main_loop(audio_device, mfrc522, mapper, buttons, bookmarks, status_sink)
 }
 
 fn main() {
@@ -283,6 +627,60 @@ fn main() {
                 .takes_value(true)
                 .required(true),
         )
+        .arg(
+            Arg::with_name("btn_next")
+                .long("btn-next")
+                .value_name("GPIO")
+                .help("GPIO pin of the next-track button")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("btn_prev")
+                .long("btn-prev")
+                .value_name("GPIO")
+                .help("GPIO pin of the previous-track button")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("btn_pause")
+                .long("btn-pause")
+                .value_name("GPIO")
+                .help("GPIO pin of the pause button")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("btn_vol_up")
+                .long("btn-vol-up")
+                .value_name("GPIO")
+                .help("GPIO pin of the volume-up button")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("btn_vol_down")
+                .long("btn-vol-down")
+                .value_name("GPIO")
+                .help("GPIO pin of the volume-down button")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("state_file")
+                .long("state-file")
+                .value_name("FILE")
+                .help(
+                    "File where the last played track index per card is persisted \
+                     (only meaningful for mappings without a [random] mode; shuffled \
+                     mappings re-shuffle on every scan, so the bookmarked index may \
+                     not land back on the same song)",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("status_sink")
+                .long("status-sink")
+                .value_name("TARGET")
+                .help("Where to publish player status events: 'stdout' or a unix socket path")
+                .takes_value(true),
+        )
         .get_matches();
     debug!("Init done");
     match run(matches) {
@@ -294,3 +692,119 @@ fn main() {
         }
     }
 }
+
+#[cfg(test)]
+mod audio_file_tests {
+    use super::*;
+
+    #[test]
+    fn accepts_known_extensions_case_insensitively() {
+        assert!(is_audio_file(Path::new("song.mp3")));
+        assert!(is_audio_file(Path::new("song.MP3")));
+        assert!(is_audio_file(Path::new("song.Flac")));
+    }
+
+    #[test]
+    fn rejects_other_extensions_and_no_extension() {
+        assert!(!is_audio_file(Path::new("cover.jpg")));
+        assert!(!is_audio_file(Path::new("readme.txt")));
+        assert!(!is_audio_file(Path::new("no_extension")));
+    }
+}
+
+#[cfg(test)]
+mod mode_tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn parses_random_and_random_keep_first() {
+        assert_eq!(parse_mode("[random]").unwrap(), PlayMode::Random);
+        assert_eq!(
+            parse_mode("[random:2]").unwrap(),
+            PlayMode::RandomKeepFirst(2)
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_mode_token() {
+        assert!(parse_mode("[shuffle]").is_err());
+    }
+
+    fn write_mapping_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "rfid-audio-test-mapping-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+        write!(file, "{}", contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn bracketed_path_without_a_known_mode_falls_back_to_in_order() {
+        let path = write_mapping_file("fallback", "deadbeef /music/archive/[live]\n");
+        let maps = read_maps(path.as_os_str()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        match maps.get("deadbeef") {
+            Some(MapEntry::Playlist(mapping)) => {
+                assert_eq!(mapping.path, "/music/archive/[live]");
+                assert_eq!(mapping.mode, PlayMode::InOrder);
+            }
+            _ => panic!("expected a playlist mapping"),
+        }
+    }
+
+    #[test]
+    fn known_mode_suffix_is_still_parsed() {
+        let path = write_mapping_file("known-mode", "deadbeef /music/kids [random:2]\n");
+        let maps = read_maps(path.as_os_str()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        match maps.get("deadbeef") {
+            Some(MapEntry::Playlist(mapping)) => {
+                assert_eq!(mapping.path, "/music/kids");
+                assert_eq!(mapping.mode, PlayMode::RandomKeepFirst(2));
+            }
+            _ => panic!("expected a playlist mapping"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod playlist_tests {
+    use super::*;
+
+    #[test]
+    fn advance_on_an_empty_playlist_does_not_panic() {
+        let mut playlist = PlayList::empty();
+        assert_eq!(playlist.advance(), None);
+        assert!(playlist.done());
+    }
+
+    #[test]
+    fn advance_past_the_end_clamps_instead_of_panicking() {
+        let mut playlist = PlayList {
+            songs: vec![PathBuf::from("a.mp3")],
+            index: 0,
+        };
+        assert_eq!(playlist.advance(), None);
+        assert!(playlist.done());
+        assert_eq!(playlist.advance(), None);
+    }
+
+    #[test]
+    fn previous_before_the_start_clamps_at_zero() {
+        let mut playlist = PlayList {
+            songs: vec![PathBuf::from("a.mp3"), PathBuf::from("b.mp3")],
+            index: 0,
+        };
+        assert_eq!(playlist.previous(), Some(Path::new("a.mp3")));
+        assert_eq!(playlist.previous(), Some(Path::new("a.mp3")));
+    }
+}
@@ -0,0 +1,164 @@
+use std::io;
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlayerStatus {
+    Stopped,
+    NowPlaying,
+    Paused,
+}
+
+impl PlayerStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PlayerStatus::Stopped => "stopped",
+            PlayerStatus::NowPlaying => "now_playing",
+            PlayerStatus::Paused => "paused",
+        }
+    }
+}
+
+pub enum Event<'a> {
+    Status {
+        state: PlayerStatus,
+    },
+    CardDetected {
+        id: &'a str,
+    },
+    CardUnmapped {
+        id: &'a str,
+    },
+    NowPlaying {
+        id: &'a str,
+        path: &'a str,
+        index: usize,
+        total: usize,
+    },
+    Paused,
+    Resumed,
+    PlaylistFinished {
+        id: &'a str,
+    },
+}
+
+fn escape_json(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl<'a> Event<'a> {
+    fn to_json(&self) -> String {
+        match self {
+            Event::Status { state } => {
+                format!(r#"{{"event":"status","state":"{}"}}"#, state.as_str())
+            }
+            Event::CardDetected { id } => {
+                format!(r#"{{"event":"card_detected","id":"{}"}}"#, escape_json(id))
+            }
+            Event::CardUnmapped { id } => {
+                format!(r#"{{"event":"card_unmapped","id":"{}"}}"#, escape_json(id))
+            }
+            Event::NowPlaying {
+                id,
+                path,
+                index,
+                total,
+            } => format!(
+                r#"{{"event":"now_playing","id":"{}","path":"{}","index":{},"total":{}}}"#,
+                escape_json(id),
+                escape_json(path),
+                index,
+                total
+            ),
+            Event::Paused => r#"{"event":"paused"}"#.to_string(),
+            Event::Resumed => r#"{"event":"resumed"}"#.to_string(),
+            Event::PlaylistFinished { id } => {
+                format!(
+                    r#"{{"event":"playlist_finished","id":"{}"}}"#,
+                    escape_json(id)
+                )
+            }
+        }
+    }
+}
+
+pub trait StatusSink {
+    fn emit(&mut self, event: Event<'_>);
+}
+
+pub struct StdoutSink;
+
+impl StatusSink for StdoutSink {
+    fn emit(&mut self, event: Event<'_>) {
+        println!("{}", event.to_json());
+    }
+}
+
+pub struct UnixSocketSink {
+    stream: UnixStream,
+}
+
+impl UnixSocketSink {
+    pub fn connect(path: &Path) -> io::Result<Self> {
+        let stream = UnixStream::connect(path)?;
+        Ok(Self { stream })
+    }
+}
+
+impl StatusSink for UnixSocketSink {
+    fn emit(&mut self, event: Event<'_>) {
+        let mut line = event.to_json();
+        line.push('\n');
+        if let Err(err) = self.stream.write_all(line.as_bytes()) {
+            error!("Error writing status event: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_quotes_and_backslashes_in_path() {
+        let json = Event::NowPlaying {
+            id: "dead\"beef",
+            path: r#"/music/a"b\c.mp3"#,
+            index: 0,
+            total: 1,
+        }
+        .to_json();
+        assert_eq!(
+            json,
+            r#"{"event":"now_playing","id":"dead\"beef","path":"/music/a\"b\\c.mp3","index":0,"total":1}"#
+        );
+    }
+
+    #[test]
+    fn escapes_control_characters() {
+        let json = Event::CardDetected { id: "a\nb" }.to_json();
+        assert_eq!(json, r#"{"event":"card_detected","id":"a\nb"}"#);
+    }
+
+    #[test]
+    fn status_event_names_the_state() {
+        let json = Event::Status {
+            state: PlayerStatus::NowPlaying,
+        }
+        .to_json();
+        assert_eq!(json, r#"{"event":"status","state":"now_playing"}"#);
+    }
+}